@@ -1,20 +1,24 @@
 use core::fmt;
 use curl::easy::Easy;
-use curl::easy::WriteError;
-use curl::multi::Easy2Handle;
+use curl::easy::List;
 use dirs;
 use serde_json::Value;
 use std::cell::RefCell;
+use std::env;
 use std::error::Error;
-use std::fs::File;
-use std::fs::{self, OpenOptions};
-use std::io::Read;
+use std::fs::{self, File};
+use std::io::IsTerminal;
 use std::io::Write;
 use std::io::stdout;
 use std::path::PathBuf;
+use std::process::Command;
 use std::rc::Rc;
 
-const VALID_COMMANDS: [&str; 5] = ["help", "entry", "remove", "show", "list"];
+const VALID_COMMANDS: [&str; 6] = ["help", "entry", "remove", "show", "list", "list-sessions"];
+
+// Caps how many times a single `chat` invocation will hand a tool result
+// back to the model before giving up, so a confused model can't loop forever.
+const MAX_TOOL_ROUNDS: usize = 8;
 
 #[derive(Debug)]
 pub struct Query {
@@ -63,6 +67,7 @@ pub fn run(query: &Query) -> Result<(), Box<dyn Error>> {
             }
             "show" => show(&query.args, &config),
             "list" => list(&config),
+            "list-sessions" => list_sessions(&config)?,
             _ => chat(&query, &config)?,
         }
     } else {
@@ -72,17 +77,166 @@ pub fn run(query: &Query) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+#[derive(Debug, Clone, PartialEq)]
+enum BackendKind {
+    Ollama,
+    OpenAi,
+    Anthropic,
+}
+
+impl BackendKind {
+    fn parse(value: &str) -> Result<Self, Box<dyn Error>> {
+        match value {
+            "ollama" => Ok(BackendKind::Ollama),
+            "openai" => Ok(BackendKind::OpenAi),
+            "anthropic" => Ok(BackendKind::Anthropic),
+            _ => Err(format!("Unknown backend '{value}'. Valid backends are: ollama, openai, anthropic."))?,
+        }
+    }
+}
+
+impl fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BackendKind::Ollama => write!(f, "ollama"),
+            BackendKind::OpenAi => write!(f, "openai"),
+            BackendKind::Anthropic => write!(f, "anthropic"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Tool {
+    name: String,
+    parameters: String,
+    command: String,
+}
+
+impl Tool {
+    // Tools named with a `may_` prefix are treated as non-read-only and
+    // must be confirmed by the user before chatwith runs them.
+    fn needs_confirmation(&self) -> bool {
+        self.name.starts_with("may_")
+    }
+}
+
 #[derive(Debug, Clone)]
 struct Entry {
     name: String,
     model: String,
     options: Vec<String>,
+    system: Option<String>,
+    backend: BackendKind,
+    base_url: Option<String>,
+    api_key_env: Option<String>,
+    tools: Vec<Tool>,
 }
 
 impl fmt::Display for Entry {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{} {} {}", self.name, self.model, self.options.join(" "))
+        write!(f, "{} {} {}", self.name, self.model, self.options.join(" "))?;
+        if self.backend != BackendKind::Ollama {
+            write!(f, " backend={}", self.backend)?;
+        }
+        if let Some(base_url) = &self.base_url {
+            write!(f, " base_url={}", base_url)?;
+        }
+        if let Some(api_key_env) = &self.api_key_env {
+            write!(f, " api_key_env={}", api_key_env)?;
+        }
+        for tool in &self.tools {
+            write!(f, " --tool {} {} {}", tool.name, tool.parameters, tool.command)?;
+        }
+        if let Some(system) = &self.system {
+            write!(f, " --system {}", system)?;
+        }
+        Ok(())
+    }
+}
+
+// Splits trailing `--system <text...>` off a token list, returning the
+// options that precede it and the joined system prompt text, if any.
+fn split_system_prompt(tokens: &[&str]) -> (Vec<String>, Option<String>) {
+    match tokens.iter().position(|token| *token == "--system") {
+        Some(index) => (
+            tokens[..index].iter().map(|s| s.to_string()).collect(),
+            Some(tokens[index + 1..].join(" ")),
+        ),
+        None => (tokens.iter().map(|s| s.to_string()).collect(), None),
+    }
+}
+
+// Pulls `--tool <name> <json-schema> <command template...>` blocks out of
+// a token list (the JSON schema must be written with no spaces so it
+// survives whitespace splitting), leaving the remaining tokens behind.
+fn extract_tools<'a>(tokens: &[&'a str]) -> Result<(Vec<&'a str>, Vec<Tool>), Box<dyn Error>> {
+    let mut remaining: Vec<&str> = Vec::new();
+    let mut tools: Vec<Tool> = Vec::new();
+
+    let mut index = 0;
+    while index < tokens.len() {
+        if tokens[index] != "--tool" {
+            remaining.push(tokens[index]);
+            index += 1;
+            continue;
+        }
+
+        let name = *tokens
+            .get(index + 1)
+            .ok_or("Incomplete --tool definition: missing name.")?;
+        let parameters = *tokens.get(index + 2).ok_or(format!(
+            "Incomplete --tool definition for '{name}': missing JSON parameters schema."
+        ))?;
+
+        let mut end = index + 3;
+        while end < tokens.len() && tokens[end] != "--tool" && tokens[end] != "--system" {
+            end += 1;
+        }
+        if end == index + 3 {
+            Err(format!(
+                "Incomplete --tool definition for '{name}': missing command template."
+            ))?;
+        }
+
+        tools.push(Tool {
+            name: name.to_string(),
+            parameters: parameters.to_string(),
+            command: tokens[index + 3..end].join(" "),
+        });
+        index = end;
     }
+
+    Ok((remaining, tools))
+}
+
+// Pulls `backend=`, `base_url=`, and `api_key_env=` tokens out of an
+// entry's option list, leaving the remaining sampling options behind.
+fn extract_backend_config(
+    options: Vec<String>,
+) -> Result<(Vec<String>, BackendKind, Option<String>, Option<String>), Box<dyn Error>> {
+    let mut backend: BackendKind = BackendKind::Ollama;
+    let mut base_url: Option<String> = None;
+    let mut api_key_env: Option<String> = None;
+    let mut remaining: Vec<String> = Vec::new();
+
+    for option in options {
+        if let Some(value) = option.strip_prefix("backend=") {
+            backend = BackendKind::parse(value)?;
+        } else if let Some(value) = option.strip_prefix("base_url=") {
+            base_url = Some(value.to_string());
+        } else if let Some(value) = option.strip_prefix("api_key_env=") {
+            api_key_env = Some(value.to_string());
+        } else {
+            if !option.contains('=') {
+                Err(format!(
+                    "Invalid option '{option}': expected a key=value pair (e.g. temperature=0.2)."
+                ))?;
+            }
+            remaining.push(option);
+        }
+    }
+
+    Ok((remaining, backend, base_url, api_key_env))
 }
 
 fn parse_config(config: Vec<Entry>, lines: Vec<&str>) -> Result<Vec<Entry>, Box<dyn Error>> {
@@ -104,10 +258,19 @@ fn parse_config(config: Vec<Entry>, lines: Vec<&str>) -> Result<Vec<Entry>, Box<
                         "Invalid entry in config. Make sure the entry is not named after a valid command. Line:\n{line}"
                     ))?;
                 } else {
+                    let (rest, tools) = extract_tools(&tokens[2..])?;
+                    let (options, system) = split_system_prompt(&rest);
+                    let (options, backend, base_url, api_key_env) =
+                        extract_backend_config(options)?;
                     config.push(Entry {
                         name: String::from(tokens[0]),
                         model: String::from(tokens[1]),
-                        options: tokens[2..].iter().map(|s| s.to_string()).collect(),
+                        options,
+                        system,
+                        backend,
+                        base_url,
+                        api_key_env,
+                        tools,
                     });
                 }
             }
@@ -120,7 +283,6 @@ fn parse_config(config: Vec<Entry>, lines: Vec<&str>) -> Result<Vec<Entry>, Box<
 fn update_config(config: &Vec<Entry>, path: &PathBuf) -> Result<(), Box<dyn Error>> {
     let mut file: File = File::create(path)?;
 
-    let mut cfg_string: String = String::new();
     for entry in config {
         let line: String = format!("{}\n", entry);
         file.write_all(line.as_bytes())?;
@@ -130,20 +292,25 @@ fn update_config(config: &Vec<Entry>, path: &PathBuf) -> Result<(), Box<dyn Erro
 }
 
 fn help() {
-    println!("Valid commands: help, add, update, remove, show, list, <entry_name>");
+    println!("Valid commands: help, entry, remove, show, list, list-sessions, <entry_name>");
 }
 
 fn entry(args: &Vec<String>, config: &mut Vec<Entry>) -> Result<(), Box<dyn Error>> {
     if args.len() >= 2 {
-        let options: Vec<String> = match args.len() {
-            2 => Vec::new(),
-            _ => args[2..].to_vec(),
-        };
+        let rest: Vec<&str> = args[2..].iter().map(|s| s.as_str()).collect();
+        let (rest, tools) = extract_tools(&rest)?;
+        let (options, system) = split_system_prompt(&rest);
+        let (options, backend, base_url, api_key_env) = extract_backend_config(options)?;
 
         let new_entry: Entry = Entry {
             name: args[0].clone(),
             model: args[1].clone(),
             options,
+            system,
+            backend,
+            base_url,
+            api_key_env,
+            tools,
         };
 
         if config.iter().any(|entry| entry.name == new_entry.name) {
@@ -152,6 +319,11 @@ fn entry(args: &Vec<String>, config: &mut Vec<Entry>) -> Result<(), Box<dyn Erro
                 if entry.name == new_entry.name {
                     entry.model = new_entry.model.clone();
                     entry.options = new_entry.options.clone();
+                    entry.system = new_entry.system.clone();
+                    entry.backend = new_entry.backend.clone();
+                    entry.base_url = new_entry.base_url.clone();
+                    entry.api_key_env = new_entry.api_key_env.clone();
+                    entry.tools = new_entry.tools.clone();
                     count += 1;
                 }
             }
@@ -209,29 +381,113 @@ fn list(config: &Vec<Entry>) {
     }
 }
 
+fn list_sessions(config: &Vec<Entry>) -> Result<(), Box<dyn Error>> {
+    let sessions_dir: PathBuf = match dirs::config_dir() {
+        Some(path) => path.join("chatwith/"),
+        None => Err("No valid config path found in environment variables.")?,
+    };
+
+    let mut found: usize = 0;
+    if sessions_dir.try_exists()? {
+        for file in fs::read_dir(&sessions_dir)? {
+            let file = file?;
+            let path = file.path();
+            if path.extension().and_then(|ext| ext.to_str()) == Some("conv") {
+                let name: String = path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .unwrap_or_default()
+                    .to_string();
+                let model: &str = config
+                    .iter()
+                    .find(|entry| entry.name == name)
+                    .map(|entry| entry.model.as_str())
+                    .unwrap_or("unknown entry");
+                println!("{} ({})", name, model);
+                found += 1;
+            }
+        }
+    }
+
+    if found == 0 {
+        println!("No sessions found.");
+    }
+
+    Ok(())
+}
+
 fn chat(query: &Query, config: &Vec<Entry>) -> Result<(), Box<dyn Error>> {
     match config.iter().find(|entry| entry.name == query.command) {
         Some(entry) => {
-            let model: &String = &entry.model;
-
             let mut start_index: usize = 0;
-            if query.args.len() > 0 {
-                if query.args[0] == "-n" {
-                    remove_conversation(model);
-                    start_index = 1;
+            let mut raw: bool = !stdout().is_terminal();
+            while start_index < query.args.len() {
+                match query.args[start_index].as_str() {
+                    "-n" => {
+                        remove_conversation(&entry.name)?;
+                        start_index += 1;
+                    }
+                    "--raw" => {
+                        raw = true;
+                        start_index += 1;
+                    }
+                    _ => break,
                 }
             }
-            let mut conversation: Conversation = get_conversation(model)?;
+            let mut conversation: Conversation = get_conversation(entry)?;
             conversation.messages.push(Message {
                 role: Role::User,
                 content: query.args[start_index..].join(" "),
+                tool_call_id: None,
+                tool_calls: Vec::new(),
             });
-            let response: String = send_message(&conversation)?;
-            conversation.messages.push(Message {
-                role: Role::Assistant,
-                content: response,
-            });
-            update_conversation(&conversation);
+
+            let mut rounds: usize = 0;
+            loop {
+                match send_message(entry, &conversation, raw)? {
+                    SendResult::Message(response) => {
+                        conversation.messages.push(Message {
+                            role: Role::Assistant,
+                            content: response,
+                            tool_call_id: None,
+                            tool_calls: Vec::new(),
+                        });
+                        break;
+                    }
+                    SendResult::ToolCalls(calls) => {
+                        rounds += 1;
+                        if rounds > MAX_TOOL_ROUNDS {
+                            Err("Too many consecutive tool calls; aborting to avoid a runaway loop.")?;
+                        }
+
+                        // Record the assistant turn that requested these
+                        // calls before the results: OpenAI-compatible
+                        // backends reject a `tool` message that isn't
+                        // immediately preceded by the matching `tool_calls`.
+                        conversation.messages.push(Message {
+                            role: Role::Assistant,
+                            content: String::new(),
+                            tool_call_id: None,
+                            tool_calls: calls.clone(),
+                        });
+
+                        for call in calls {
+                            let content = match entry.tools.iter().find(|tool| tool.name == call.name) {
+                                Some(tool) => run_tool(tool, &call.arguments)?,
+                                None => format!("No tool named '{}' is configured for this entry.", call.name),
+                            };
+                            conversation.messages.push(Message {
+                                role: Role::Tool,
+                                content,
+                                tool_call_id: call.id,
+                                tool_calls: Vec::new(),
+                            });
+                        }
+                    }
+                }
+            }
+
+            update_conversation(&entry.name, &conversation)?;
         }
         None => {
             println!("No model with name {} found in config file.", query.command);
@@ -240,74 +496,569 @@ fn chat(query: &Query, config: &Vec<Entry>) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-fn send_message(conversation: &Conversation) -> Result<String, Box<dyn Error>> {
-    let request_string: String = conversation.to_json_string();
+// OpenAI and Anthropic stream server-sent events (`data: {...}`) rather
+// than bare JSON lines; strips the prefix and filters out the closing
+// `[DONE]` and keep-alive lines that carry no JSON payload.
+fn strip_sse_prefix(line: &str) -> Option<&str> {
+    let line = line.trim().strip_prefix("data:")?.trim();
+    if line.is_empty() || line == "[DONE]" {
+        return None;
+    }
+    Some(line)
+}
+
+// One function call a model asked chatwith to run, parsed out of a
+// streamed response instead of assistant text.
+#[derive(Debug, Clone)]
+struct ToolCall {
+    id: Option<String>,
+    name: String,
+    arguments: Value,
+}
+
+// Shared by Ollama and OpenAI, whose tool calls both look like
+// `{"id": ..., "function": {"name": ..., "arguments": ...}}`.
+// `arguments` may be a JSON string (OpenAI) or an inline object (Ollama).
+fn parse_function_tool_calls(calls: &[Value]) -> Option<Vec<ToolCall>> {
+    let parsed: Vec<ToolCall> = calls
+        .iter()
+        .filter_map(|call| {
+            let function = call.get("function")?;
+            let name = function.get("name")?.as_str()?.to_string();
+            let arguments = match function.get("arguments")? {
+                Value::String(raw) => serde_json::from_str(raw).unwrap_or(Value::Null),
+                value => value.clone(),
+            };
+            Some(ToolCall {
+                id: call.get("id").and_then(|id| id.as_str()).map(String::from),
+                name,
+                arguments,
+            })
+        })
+        .collect();
+
+    if parsed.is_empty() { None } else { Some(parsed) }
+}
+
+// A chat backend knows how to build an HTTP request from a conversation,
+// how to pull assistant text out of one streamed response chunk, and how
+// to recognize a chunk that requests a tool call instead.
+trait Backend {
+    fn build_request(&self, conversation: &Conversation) -> (String, Vec<String>, String);
+    fn parse_chunk(&self, data: &[u8]) -> Option<String>;
+    fn parse_tool_calls(&self, data: &[u8]) -> Option<Vec<ToolCall>>;
+}
+
+struct OllamaBackend;
+
+impl Backend for OllamaBackend {
+    fn build_request(&self, conversation: &Conversation) -> (String, Vec<String>, String) {
+        (
+            String::from("http://localhost:11434/api/chat"),
+            Vec::new(),
+            conversation.to_json_string(),
+        )
+    }
+
+    fn parse_chunk(&self, data: &[u8]) -> Option<String> {
+        let json: Value = serde_json::from_str(&String::from_utf8_lossy(data)).ok()?;
+        json.get("message")
+            .and_then(|message| message.get("content"))
+            .and_then(|content| content.as_str())
+            .map(String::from)
+    }
+
+    fn parse_tool_calls(&self, data: &[u8]) -> Option<Vec<ToolCall>> {
+        let json: Value = serde_json::from_str(&String::from_utf8_lossy(data)).ok()?;
+        let calls = json.get("message")?.get("tool_calls")?.as_array()?;
+        parse_function_tool_calls(calls)
+    }
+}
+
+// OpenAI streams each tool call's `name`/`arguments` as fragments spread
+// across many `delta.tool_calls` events, matched up by `index` rather
+// than handed over whole. Accumulated here until the chunk carrying
+// `finish_reason":"tool_calls"` signals the call is complete.
+#[derive(Default)]
+struct ToolCallFragment {
+    id: Option<String>,
+    name: String,
+    arguments: String,
+}
+
+struct OpenAiBackend {
+    base_url: String,
+    api_key_env: String,
+    tool_call_fragments: RefCell<Vec<ToolCallFragment>>,
+}
+
+impl Backend for OpenAiBackend {
+    fn build_request(&self, conversation: &Conversation) -> (String, Vec<String>, String) {
+        let url = format!("{}/chat/completions", self.base_url.trim_end_matches('/'));
+        let headers = vec![
+            format!("Authorization: Bearer {}", env::var(&self.api_key_env).unwrap_or_default()),
+            String::from("Content-Type: application/json"),
+        ];
+        (url, headers, conversation.to_openai_json_string())
+    }
+
+    fn parse_chunk(&self, data: &[u8]) -> Option<String> {
+        let raw = String::from_utf8_lossy(data);
+        let line = strip_sse_prefix(&raw)?;
+        let json: Value = serde_json::from_str(line).ok()?;
+        json.get("choices")?
+            .get(0)?
+            .get("delta")?
+            .get("content")
+            .and_then(|content| content.as_str())
+            .map(String::from)
+    }
+
+    fn parse_tool_calls(&self, data: &[u8]) -> Option<Vec<ToolCall>> {
+        let raw = String::from_utf8_lossy(data);
+        let line = strip_sse_prefix(&raw)?;
+        let json: Value = serde_json::from_str(line).ok()?;
+        let choice = json.get("choices")?.get(0)?;
+
+        if let Some(deltas) = choice
+            .get("delta")
+            .and_then(|delta| delta.get("tool_calls"))
+            .and_then(|tool_calls| tool_calls.as_array())
+        {
+            let mut fragments = self.tool_call_fragments.borrow_mut();
+            for delta in deltas {
+                let index = delta.get("index").and_then(|index| index.as_u64()).unwrap_or(0) as usize;
+                while fragments.len() <= index {
+                    fragments.push(ToolCallFragment::default());
+                }
+                let fragment = &mut fragments[index];
+                if let Some(id) = delta.get("id").and_then(|id| id.as_str()) {
+                    fragment.id = Some(id.to_string());
+                }
+                if let Some(function) = delta.get("function") {
+                    if let Some(name) = function.get("name").and_then(|name| name.as_str()) {
+                        fragment.name.push_str(name);
+                    }
+                    if let Some(arguments) = function.get("arguments").and_then(|args| args.as_str()) {
+                        fragment.arguments.push_str(arguments);
+                    }
+                }
+            }
+        }
+
+        if choice.get("finish_reason").and_then(|reason| reason.as_str()) != Some("tool_calls") {
+            return None;
+        }
+
+        let fragments = self.tool_call_fragments.borrow();
+        let calls: Vec<ToolCall> = fragments
+            .iter()
+            .map(|fragment| ToolCall {
+                id: fragment.id.clone(),
+                name: fragment.name.clone(),
+                arguments: serde_json::from_str(&fragment.arguments).unwrap_or(Value::Null),
+            })
+            .collect();
+
+        if calls.is_empty() { None } else { Some(calls) }
+    }
+}
+
+// Anthropic streams a tool call's input as incremental `partial_json`
+// deltas against a `content_block` index rather than handing it over in
+// one event, so the pieces are accumulated here until that block's
+// `content_block_stop` arrives.
+#[derive(Clone)]
+struct AnthropicToolFragment {
+    id: Option<String>,
+    name: String,
+    partial_json: String,
+}
+
+struct AnthropicBackend {
+    base_url: String,
+    api_key_env: String,
+    tool_fragments: RefCell<Vec<Option<AnthropicToolFragment>>>,
+}
+
+impl Backend for AnthropicBackend {
+    fn build_request(&self, conversation: &Conversation) -> (String, Vec<String>, String) {
+        let url = format!("{}/v1/messages", self.base_url.trim_end_matches('/'));
+        let headers = vec![
+            format!("x-api-key: {}", env::var(&self.api_key_env).unwrap_or_default()),
+            String::from("anthropic-version: 2023-06-01"),
+            String::from("Content-Type: application/json"),
+        ];
+        (url, headers, conversation.to_anthropic_json_string())
+    }
+
+    fn parse_chunk(&self, data: &[u8]) -> Option<String> {
+        let raw = String::from_utf8_lossy(data);
+        let line = strip_sse_prefix(&raw)?;
+        let json: Value = serde_json::from_str(line).ok()?;
+        if json.get("type").and_then(|kind| kind.as_str()) != Some("content_block_delta") {
+            return None;
+        }
+        json.get("delta")?
+            .get("text")
+            .and_then(|text| text.as_str())
+            .map(String::from)
+    }
+
+    fn parse_tool_calls(&self, data: &[u8]) -> Option<Vec<ToolCall>> {
+        let raw = String::from_utf8_lossy(data);
+        let line = strip_sse_prefix(&raw)?;
+        let json: Value = serde_json::from_str(line).ok()?;
+        let kind = json.get("type").and_then(|kind| kind.as_str())?;
+        let index = json.get("index").and_then(|index| index.as_u64()).unwrap_or(0) as usize;
+
+        match kind {
+            "content_block_start" => {
+                let block = json.get("content_block")?;
+                if block.get("type").and_then(|kind| kind.as_str()) != Some("tool_use") {
+                    return None;
+                }
+                let mut fragments = self.tool_fragments.borrow_mut();
+                while fragments.len() <= index {
+                    fragments.push(None);
+                }
+                fragments[index] = Some(AnthropicToolFragment {
+                    id: block.get("id").and_then(|id| id.as_str()).map(String::from),
+                    name: block.get("name")?.as_str()?.to_string(),
+                    partial_json: String::new(),
+                });
+                None
+            }
+            "content_block_delta" => {
+                let delta = json.get("delta")?;
+                if delta.get("type").and_then(|kind| kind.as_str()) != Some("input_json_delta") {
+                    return None;
+                }
+                let partial = delta.get("partial_json").and_then(|json| json.as_str())?;
+                if let Some(Some(fragment)) = self.tool_fragments.borrow_mut().get_mut(index) {
+                    fragment.partial_json.push_str(partial);
+                }
+                None
+            }
+            "content_block_stop" => {
+                let fragment = self.tool_fragments.borrow().get(index).cloned().flatten()?;
+                let arguments = if fragment.partial_json.trim().is_empty() {
+                    Value::Object(serde_json::Map::new())
+                } else {
+                    serde_json::from_str(&fragment.partial_json).unwrap_or(Value::Null)
+                };
+                Some(vec![ToolCall {
+                    id: fragment.id,
+                    name: fragment.name,
+                    arguments,
+                }])
+            }
+            _ => None,
+        }
+    }
+}
+
+fn backend_for(entry: &Entry) -> Box<dyn Backend> {
+    match entry.backend {
+        BackendKind::Ollama => Box::new(OllamaBackend),
+        BackendKind::OpenAi => Box::new(OpenAiBackend {
+            base_url: entry
+                .base_url
+                .clone()
+                .unwrap_or_else(|| String::from("https://api.openai.com/v1")),
+            api_key_env: entry
+                .api_key_env
+                .clone()
+                .unwrap_or_else(|| String::from("OPENAI_API_KEY")),
+            tool_call_fragments: RefCell::new(Vec::new()),
+        }),
+        BackendKind::Anthropic => Box::new(AnthropicBackend {
+            base_url: entry
+                .base_url
+                .clone()
+                .unwrap_or_else(|| String::from("https://api.anthropic.com")),
+            api_key_env: entry
+                .api_key_env
+                .clone()
+                .unwrap_or_else(|| String::from("ANTHROPIC_API_KEY")),
+            tool_fragments: RefCell::new(Vec::new()),
+        }),
+    }
+}
+
+// The outcome of one round-trip to a backend: either the model answered
+// in plain text, or it asked chatwith to run one or more tools first.
+enum SendResult {
+    Message(String),
+    ToolCalls(Vec<ToolCall>),
+}
+
+// Parses one newline-stripped stream line: recognizes a backend's tool
+// calls or assistant text chunk, prints/buffers the chunk, and tracks
+// whether a `<think>` block is open. Shared between the streaming write
+// callback and the flush of a trailing unterminated line once the
+// transfer completes.
+fn process_stream_line(
+    backend: &dyn Backend,
+    line: &[u8],
+    raw: bool,
+    is_response: &mut bool,
+    response: &RefCell<String>,
+    tool_calls: &RefCell<Vec<ToolCall>>,
+) {
+    if line.is_empty() {
+        return;
+    }
+
+    if let Some(calls) = backend.parse_tool_calls(line) {
+        tool_calls.borrow_mut().extend(calls);
+        return;
+    }
+
+    // `parse_chunk` hands back already-decoded text, so newlines and
+    // backslashes in it are real content and are kept as they are.
+    let output = match backend.parse_chunk(line) {
+        Some(content) => content,
+        None => return,
+    };
+
+    if output.contains("<think>") {
+        if !raw {
+            print!("\x1B[90m");
+        }
+        *is_response = false;
+    }
+
+    if raw || !*is_response {
+        print!("{}", output);
+    }
+    if *is_response {
+        response.borrow_mut().push_str(&output);
+    }
+
+    if output.contains("</think>") {
+        if !raw {
+            print!("\x1B[39m");
+        }
+        *is_response = true;
+    }
+
+    stdout().flush();
+}
+
+fn send_message(entry: &Entry, conversation: &Conversation, raw: bool) -> Result<SendResult, Box<dyn Error>> {
+    let backend: Box<dyn Backend> = backend_for(entry);
+    let (url, headers, request_string) = backend.build_request(conversation);
     let request = request_string.as_bytes();
     let mut easy = Easy::new();
-    easy.url("http://localhost:11434/api/chat")?;
+    easy.url(&url)?;
     easy.post(true)?;
 
+    if !headers.is_empty() {
+        let mut header_list = List::new();
+        for header in &headers {
+            header_list.append(header)?;
+        }
+        easy.http_headers(header_list)?;
+    }
+
     let response = Rc::new(RefCell::new(String::new()));
     let response_clone = response.clone();
     let mut is_response: bool = true; // indicates whether thinking-block has ended
 
-    let first_bold: bool = true;
+    let tool_calls: Rc<RefCell<Vec<ToolCall>>> = Rc::new(RefCell::new(Vec::new()));
+    let tool_calls_clone = tool_calls.clone();
+
+    // curl hands the write callback arbitrary byte boundaries, not whole
+    // JSON objects, so incoming bytes are buffered here and only complete
+    // newline-delimited lines are handed to the backend for parsing.
+    let line_buffer: Rc<RefCell<Vec<u8>>> = Rc::new(RefCell::new(Vec::new()));
+
     easy.post_fields_copy(request)?;
     let mut transfer = easy.transfer();
     transfer.write_function(|data: &[u8]| {
-        let json: Value =
-            serde_json::from_str(String::from_utf8(data.to_vec()).unwrap().as_str()).unwrap();
-        let mut output = match json.get("message").and_then(|msg| msg.get("content")) {
-            Some(content) => content.to_string().replace("\"", ""),
-            None => {
-                eprintln!(
-                    "No value message.content in response json. Response is: {}",
-                    json
-                );
-                return Ok(data.len());
-            }
-        };
-        let newlines: usize = output.matches("\\n").count();
-        if newlines > 0 {
-            output = output.replace("\\n", "").replace("\\", ""); // sanitize newlines and escape slashes
+        line_buffer.borrow_mut().extend_from_slice(data);
+
+        loop {
+            let newline_index = line_buffer.borrow().iter().position(|byte| *byte == b'\n');
+            let line: Vec<u8> = match newline_index {
+                Some(index) => line_buffer.borrow_mut().drain(..=index).collect(),
+                None => break,
+            };
+            let line = &line[..line.len() - 1];
+            process_stream_line(&*backend, line, raw, &mut is_response, &response_clone, &tool_calls_clone);
         }
 
-        if output.contains("<think>") {
-            print!("\x1B[90m");
-            is_response = false;
+        Ok(data.len())
+    })?;
+    transfer.perform()?;
+    drop(transfer);
+
+    // A backend that doesn't newline-terminate its final object leaves a
+    // trailing partial line in the buffer; flush it once the transfer
+    // completes instead of dropping that last chunk.
+    let remainder: Vec<u8> = line_buffer.borrow_mut().drain(..).collect();
+    process_stream_line(&*backend, &remainder, raw, &mut is_response, &response, &tool_calls);
+
+    if !tool_calls.borrow().is_empty() {
+        return Ok(SendResult::ToolCalls(tool_calls.borrow().clone()));
+    }
+
+    let result: String = response.borrow().clone();
+    if !raw {
+        print!("{}", render_markdown(&result));
+        stdout().flush()?;
+    }
+    Ok(SendResult::Message(result))
+}
+
+// Renders assistant markdown as ANSI terminal text: fenced code blocks get
+// syntax highlighting, headings and bold get simple styling, and list
+// markers get a bullet. Used instead of live streaming so a fenced code
+// block can be highlighted as a whole once its closing ``` has arrived;
+// `--raw` or a non-TTY stdout skips this and prints the stream as-is.
+fn render_markdown(text: &str) -> String {
+    let mut output = String::new();
+    let mut in_code_block = false;
+    let mut code_lang = String::new();
+    let mut code_buffer = String::new();
+
+    for line in text.lines() {
+        if let Some(lang) = line.trim_start().strip_prefix("```") {
+            if in_code_block {
+                output.push_str(&highlight_code(&code_buffer, &code_lang));
+                code_buffer.clear();
+                in_code_block = false;
+            } else {
+                code_lang = lang.trim().to_string();
+                in_code_block = true;
+            }
+            continue;
         }
 
-        print!("{}", output);
-        if is_response {
-            response_clone.borrow_mut().push_str(&output);
+        if in_code_block {
+            code_buffer.push_str(line);
+            code_buffer.push('\n');
+            continue;
         }
 
-        if output.contains("</think>") {
-            print!("\x1B[39m");
-            is_response = true;
+        output.push_str(&render_markdown_line(line));
+        output.push('\n');
+    }
+
+    if in_code_block {
+        // Unterminated fence: highlight what we have rather than drop it.
+        output.push_str(&highlight_code(&code_buffer, &code_lang));
+    }
+
+    output
+}
+
+fn render_markdown_line(line: &str) -> String {
+    let trimmed = line.trim_start();
+    for prefix in ["### ", "## ", "# "] {
+        if let Some(heading) = trimmed.strip_prefix(prefix) {
+            return format!("\x1B[1;4m{}\x1B[0m", render_inline(heading));
         }
+    }
+    if let Some(item) = trimmed.strip_prefix("- ").or_else(|| trimmed.strip_prefix("* ")) {
+        return format!("  \u{2022} {}", render_inline(item));
+    }
+    render_inline(line)
+}
 
-        for _ in 0..newlines {
-            println!();
-            if is_response {
-                response_clone.borrow_mut().push_str("\n");
+// Replaces `**bold**` spans with bold ANSI escapes; leaves everything else
+// untouched since the rest of the renderer only needs headings/lists/code.
+fn render_inline(text: &str) -> String {
+    let mut output = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("**") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        match after.find("**") {
+            Some(end) => {
+                output.push_str("\x1B[1m");
+                output.push_str(&after[..end]);
+                output.push_str("\x1B[0m");
+                rest = &after[end + 2..];
+            }
+            None => {
+                output.push_str("**");
+                rest = after;
+                break;
             }
         }
+    }
+    output.push_str(rest);
+    output
+}
 
-        stdout().flush();
-        Ok(data.len())
-    })?;
-    transfer.perform()?;
+// Syntax-highlights one fenced code block using syntect, falling back to
+// plain text for an unrecognized or missing language tag.
+fn highlight_code(code: &str, lang: &str) -> String {
+    let syntax_set = syntect::parsing::SyntaxSet::load_defaults_newlines();
+    let theme_set = syntect::highlighting::ThemeSet::load_defaults();
+    let syntax = syntax_set
+        .find_syntax_by_token(lang)
+        .unwrap_or_else(|| syntax_set.find_syntax_plain_text());
+    let theme = &theme_set.themes["base16-ocean.dark"];
+    let mut highlighter = syntect::easy::HighlightLines::new(syntax, theme);
 
-    Ok(response.borrow().clone())
+    let mut highlighted = String::new();
+    for line in syntect::util::LinesWithEndings::from(code) {
+        let ranges = highlighter.highlight_line(line, &syntax_set).unwrap_or_default();
+        highlighted.push_str(&syntect::util::as_24_bit_terminal_escaped(&ranges[..], false));
+    }
+    highlighted.push_str("\x1B[0m");
+    highlighted
+}
+
+// Substitutes `{key}` placeholders in a tool's command template with the
+// matching argument values the model supplied.
+fn substitute_arguments(template: &str, arguments: &Value) -> String {
+    let mut command = template.to_string();
+    if let Some(object) = arguments.as_object() {
+        for (key, value) in object {
+            let replacement = value.as_str().map(String::from).unwrap_or_else(|| value.to_string());
+            command = command.replace(&format!("{{{key}}}"), &replacement);
+        }
+    }
+    command
+}
+
+// Runs a configured tool, prompting for confirmation first unless its
+// name marks it read-only (i.e. it doesn't start with `may_`).
+fn run_tool(tool: &Tool, arguments: &Value) -> Result<String, Box<dyn Error>> {
+    let command = substitute_arguments(&tool.command, arguments);
+
+    if tool.needs_confirmation() {
+        print!("Run `{command}`? [y/N] ");
+        stdout().flush()?;
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        if !answer.trim().eq_ignore_ascii_case("y") {
+            return Ok(String::from("Tool call declined by user."));
+        }
+    }
+
+    let output = Command::new("sh").arg("-c").arg(&command).output()?;
+    let mut result = String::from_utf8_lossy(&output.stdout).to_string();
+    if !output.status.success() {
+        result.push_str(&String::from_utf8_lossy(&output.stderr));
+    }
+
+    Ok(result)
 }
 
 struct Conversation {
     model: String,
     messages: Vec<Message>,
+    options: Vec<String>,
+    tools: Vec<Tool>,
 }
 
 impl Conversation {
+    // Ollama's chat body: sampling options nest under `"options"`.
     fn to_json_string(&self) -> String {
         let mut json_string: String =
             format!("{}{}{}", r#"{"model":""#, self.model, r#"","messages":["#);
@@ -316,56 +1067,287 @@ impl Conversation {
             json_string.push(',');
         }
         json_string.pop();
-        json_string.push_str(r#"],"stream":true}"#);
+        json_string.push(']');
+        json_string.push_str(&options_to_json(&self.options));
+        json_string.push_str(&tools_to_openai_json(&self.tools));
+        json_string.push_str(r#","stream":true}"#);
+        json_string
+    }
+
+    // OpenAI's chat completions API takes sampling options (`temperature`,
+    // `top_p`, etc.) as top-level request fields instead of Ollama's
+    // nested `"options"` object, and rejects unknown fields outright.
+    fn to_openai_json_string(&self) -> String {
+        let mut json_string: String =
+            format!("{}{}{}", r#"{"model":""#, self.model, r#"","messages":["#);
+        for message in &self.messages {
+            json_string.push_str(&message.to_json_string());
+            json_string.push(',');
+        }
+        json_string.pop();
+        json_string.push(']');
+        json_string.push_str(&options_to_openai_json(&self.options));
+        json_string.push_str(&tools_to_openai_json(&self.tools));
+        json_string.push_str(r#","stream":true}"#);
+        json_string
+    }
+
+    // Anthropic's Messages API takes the system prompt as a top-level
+    // field instead of a message with role "system", requires
+    // `max_tokens` on every request, and has its own shape for tool
+    // turns: an assistant `tool_use` content block instead of a bare
+    // `tool_calls` field, and a `tool_result` block inside a `user`
+    // message instead of a `role:"tool"` message. Consecutive Role::Tool
+    // messages (one per call in a round) are folded into a single user
+    // turn, since Anthropic requires strictly alternating roles.
+    fn to_anthropic_json_string(&self) -> String {
+        let mut json_string: String = format!("{}{}{}", r#"{"model":""#, self.model, r#"","#);
+
+        if let Some(system) = self.messages.iter().find(|message| message.role == Role::System) {
+            json_string.push_str(&format!(r#""system":{},"#, json_escape(&system.content)));
+        }
+
+        json_string.push_str(r#""messages":["#);
+        // `get_conversation` injects `--system` as a normal Role::System
+        // message so Ollama/OpenAI can send it inline; Anthropic rejects a
+        // `system`-role entry in `messages` (it only takes the top-level
+        // `"system"` field above), so it's excluded here regardless of
+        // where in the history it ended up.
+        let turns: Vec<&Message> = self
+            .messages
+            .iter()
+            .filter(|message| message.role != Role::System)
+            .collect();
+
+        let mut rendered: Vec<String> = Vec::new();
+        let mut index = 0;
+        while index < turns.len() {
+            if turns[index].role == Role::Tool {
+                let mut blocks: Vec<String> = Vec::new();
+                while index < turns.len() && turns[index].role == Role::Tool {
+                    blocks.push(anthropic_tool_result_json(turns[index]));
+                    index += 1;
+                }
+                rendered.push(format!(r#"{{"role":"user","content":[{}]}}"#, blocks.join(",")));
+                continue;
+            }
+
+            rendered.push(turns[index].to_anthropic_json_string());
+            index += 1;
+        }
+
+        json_string.push_str(&rendered.join(","));
+        json_string.push(']');
+        json_string.push_str(&tools_to_anthropic_json(&self.tools));
+        json_string.push_str(r#","max_tokens":4096,"stream":true}"#);
         json_string
     }
 }
 
+// Renders one Role::Tool message as an Anthropic `tool_result` content
+// block, keyed back to its call via `tool_use_id`.
+fn anthropic_tool_result_json(message: &Message) -> String {
+    format!(
+        r#"{{"type":"tool_result","tool_use_id":{},"content":{}}}"#,
+        json_escape(&message.tool_call_id.clone().unwrap_or_default()),
+        json_escape(&message.content)
+    )
+}
+
+// Renders `key=value` option tokens (e.g. `temperature=0.2`) as JSON
+// `"key":value` pairs, with numeric values emitted as JSON numbers rather
+// than strings.
+fn options_to_pairs(options: &[String]) -> Vec<String> {
+    let mut pairs: Vec<String> = Vec::new();
+    for option in options {
+        if let Some((key, value)) = option.split_once('=') {
+            if value.parse::<f64>().is_ok() {
+                pairs.push(format!(r#""{}":{}"#, key, value));
+            } else {
+                pairs.push(format!(r#""{}":"{}""#, key, value));
+            }
+        }
+    }
+    pairs
+}
+
+// Nests sampling options under Ollama's `"options"` object. Returns an
+// empty string when there are no options to send.
+fn options_to_json(options: &[String]) -> String {
+    let pairs = options_to_pairs(options);
+    if pairs.is_empty() {
+        String::new()
+    } else {
+        format!(r#","options":{{{}}}"#, pairs.join(","))
+    }
+}
+
+// OpenAI expects sampling options as top-level request fields rather than
+// nested under an `"options"` object, and rejects unknown body fields.
+fn options_to_openai_json(options: &[String]) -> String {
+    let pairs = options_to_pairs(options);
+    if pairs.is_empty() {
+        String::new()
+    } else {
+        format!(",{}", pairs.join(","))
+    }
+}
+
+// Renders configured tools in the OpenAI/Ollama function-calling shape.
+fn tools_to_openai_json(tools: &[Tool]) -> String {
+    if tools.is_empty() {
+        return String::new();
+    }
+
+    let entries: Vec<String> = tools
+        .iter()
+        .map(|tool| {
+            format!(
+                r#"{{"type":"function","function":{{"name":"{}","parameters":{}}}}}"#,
+                tool.name, tool.parameters
+            )
+        })
+        .collect();
+    format!(r#","tools":[{}]"#, entries.join(","))
+}
+
+// Renders configured tools in Anthropic's top-level tool shape.
+fn tools_to_anthropic_json(tools: &[Tool]) -> String {
+    if tools.is_empty() {
+        return String::new();
+    }
+
+    let entries: Vec<String> = tools
+        .iter()
+        .map(|tool| format!(r#"{{"name":"{}","input_schema":{}}}"#, tool.name, tool.parameters))
+        .collect();
+    format!(r#","tools":[{}]"#, entries.join(","))
+}
+
+// Escapes a string as a JSON string literal, including the surrounding
+// quotes. Message content and `--system` text are free-form and may
+// contain `"`, `\`, or newlines, so they can't be interpolated raw into
+// the hand-built request bodies.
+fn json_escape(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| String::from(r#""""#))
+}
+
 #[derive(Debug)]
 struct Message {
     role: Role,
     content: String,
+    // Set on Role::Tool messages so OpenAI-compatible backends can match
+    // the tool result back to the call that requested it.
+    tool_call_id: Option<String>,
+    // Set on the Role::Assistant message that requested tool calls, so it
+    // can be replayed to OpenAI-compatible backends; they reject a `tool`
+    // message that isn't immediately preceded by the assistant turn that
+    // carried the matching `tool_calls`.
+    tool_calls: Vec<ToolCall>,
 }
 
 impl fmt::Display for Message {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "<{}>\n{}\n</{}>\n", self.role, self.content, self.role)
+        write!(f, "<{}>\n{}\n", self.role, self.content)?;
+        if let Some(tool_call_id) = &self.tool_call_id {
+            write!(f, "<tool_call_id>{}</tool_call_id>\n", tool_call_id)?;
+        }
+        if !self.tool_calls.is_empty() {
+            write!(f, "<tool_calls>{}</tool_calls>\n", tool_calls_to_json(&self.tool_calls))?;
+        }
+        write!(f, "</{}>\n", self.role)
     }
 }
 
 impl Message {
     fn to_json_string(&self) -> String {
-        format!(
-            "{}{}{}{}{}",
-            r#"{"role":""#, self.role, r#"","content":""#, self.content, r#""}"#
-        )
+        let mut json_string = format!(
+            r#"{{"role":"{}","content":{}}}"#,
+            self.role,
+            json_escape(&self.content)
+        );
+        if let Some(tool_call_id) = &self.tool_call_id {
+            json_string.pop();
+            json_string.push_str(&format!(r#","tool_call_id":{}}}"#, json_escape(tool_call_id)));
+        }
+        if !self.tool_calls.is_empty() {
+            json_string.pop();
+            json_string.push_str(&format!(r#","tool_calls":{}}}"#, tool_calls_to_json(&self.tool_calls)));
+        }
+        json_string
+    }
+
+    // An assistant turn that requested tool calls renders as Anthropic
+    // `tool_use` content blocks instead of a `tool_calls` field; anything
+    // else uses the same shape OpenAI/Ollama take.
+    fn to_anthropic_json_string(&self) -> String {
+        if self.tool_calls.is_empty() {
+            return self.to_json_string();
+        }
+
+        let blocks: Vec<String> = self
+            .tool_calls
+            .iter()
+            .map(|call| {
+                format!(
+                    r#"{{"type":"tool_use","id":{},"name":{},"input":{}}}"#,
+                    json_escape(&call.id.clone().unwrap_or_default()),
+                    json_escape(&call.name),
+                    call.arguments
+                )
+            })
+            .collect();
+        format!(r#"{{"role":"assistant","content":[{}]}}"#, blocks.join(","))
     }
 }
 
+// Renders tool calls in the OpenAI/Ollama function-calling shape, the
+// mirror image of `parse_function_tool_calls`, so a round-tripped
+// assistant message can be replayed to the backend (or reloaded from the
+// transcript) with its tool calls intact.
+fn tool_calls_to_json(calls: &[ToolCall]) -> String {
+    let entries: Vec<String> = calls
+        .iter()
+        .map(|call| {
+            let id = call.id.clone().unwrap_or_default();
+            let arguments = serde_json::to_string(&call.arguments.to_string()).unwrap_or_default();
+            format!(
+                r#"{{"id":"{}","type":"function","function":{{"name":"{}","arguments":{}}}}}"#,
+                id, call.name, arguments
+            )
+        })
+        .collect();
+    format!("[{}]", entries.join(","))
+}
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 enum Role {
+    System,
     User,
     Assistant,
+    Tool,
     None,
 }
 
 impl fmt::Display for Role {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
+            Role::System => write!(f, "system"),
             Role::User => write!(f, "user"),
             Role::Assistant => write!(f, "assistant"),
+            Role::Tool => write!(f, "tool"),
             Role::None => write!(f, "none"),
         }
     }
 }
 
-fn remove_conversation(model: &String) -> Result<(), Box<dyn Error>> {
+fn remove_conversation(session: &String) -> Result<(), Box<dyn Error>> {
     let conversation_path: PathBuf = match dirs::config_dir() {
-        Some(path) => path.join("chatwith/").join(format!("{}{}", model, ".conv")),
+        Some(path) => path.join("chatwith/").join(format!("{}{}", session, ".conv")),
         None => Err("No valid config path found in environment variables.")?,
     };
 
-    let mut file_result: Result<File, std::io::Error> =
+    let file_result: Result<File, std::io::Error> =
         File::options().write(true).open(conversation_path);
     if let Ok(mut file) = file_result {
         file.set_len(0)?;
@@ -375,44 +1357,109 @@ fn remove_conversation(model: &String) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-fn get_conversation(model: &String) -> Result<Conversation, Box<dyn Error>> {
+fn get_conversation(entry: &Entry) -> Result<Conversation, Box<dyn Error>> {
     let conversation_path: PathBuf = match dirs::config_dir() {
-        Some(path) => path.join("chatwith/").join(format!("{}{}", model, ".conv")),
+        Some(path) => path
+            .join("chatwith/")
+            .join(format!("{}{}", entry.name, ".conv")),
         None => Err("No valid config path found in environment variables.")?,
     };
 
-    if conversation_path.try_exists()? {
-        return Ok(parse_conversation(
-            model,
+    let mut conversation: Conversation = if conversation_path.try_exists()? {
+        parse_conversation(
+            &entry.model,
             fs::read_to_string(&conversation_path)?.lines().collect(),
-        ));
+        )
+    } else {
+        Conversation {
+            model: entry.model.clone(),
+            messages: Vec::new(),
+            options: Vec::new(),
+            tools: Vec::new(),
+        }
+    };
+    conversation.options = entry.options.clone();
+    conversation.tools = entry.tools.clone();
+
+    // Reconcile the leading system message against the entry's current
+    // `--system` on every load, rather than trusting whatever was last
+    // persisted: otherwise an edited or removed persona never takes
+    // effect until the session is wiped with `-n`.
+    if conversation
+        .messages
+        .first()
+        .is_some_and(|message| message.role == Role::System)
+    {
+        conversation.messages.remove(0);
+    }
+    if let Some(system) = &entry.system {
+        conversation.messages.insert(
+            0,
+            Message {
+                role: Role::System,
+                content: system.clone(),
+                tool_call_id: None,
+                tool_calls: Vec::new(),
+            },
+        );
     }
 
-    Ok(Conversation {
-        model: model.clone(),
-        messages: Vec::new(),
-    })
+    Ok(conversation)
 }
 
 fn parse_conversation(model: &String, lines: Vec<&str>) -> Conversation {
     let mut conversation: Conversation = Conversation {
         model: model.clone(),
         messages: Vec::new(),
+        options: Vec::new(),
+        tools: Vec::new(),
     };
 
     let mut current_role: Role = Role::None;
     for line in lines {
         match line {
+            "<system>" => current_role = Role::System,
+            "</system>" => current_role = Role::None,
             "<user>" => current_role = Role::User,
             "</user>" => current_role = Role::None,
             "<assistant>" => current_role = Role::Assistant,
             "</assistant>" => current_role = Role::None,
+            "<tool>" => current_role = Role::Tool,
+            "</tool>" => current_role = Role::None,
             _ => {
                 if current_role != Role::None {
+                    if let Some(raw) = line
+                        .trim()
+                        .strip_prefix("<tool_calls>")
+                        .and_then(|rest| rest.strip_suffix("</tool_calls>"))
+                    {
+                        if let Some(message) = conversation.messages.last_mut() {
+                            if let Ok(Value::Array(calls)) = serde_json::from_str(raw) {
+                                if let Some(parsed) = parse_function_tool_calls(&calls) {
+                                    message.tool_calls = parsed;
+                                }
+                            }
+                        }
+                        continue;
+                    }
+
+                    if let Some(raw) = line
+                        .trim()
+                        .strip_prefix("<tool_call_id>")
+                        .and_then(|rest| rest.strip_suffix("</tool_call_id>"))
+                    {
+                        if let Some(message) = conversation.messages.last_mut() {
+                            message.tool_call_id = Some(raw.to_string());
+                        }
+                        continue;
+                    }
+
                     if conversation.messages.len() == 0 {
                         conversation.messages.push(Message {
-                            role: current_role.clone(),
+                            role: current_role,
                             content: String::new(),
+                            tool_call_id: None,
+                            tool_calls: Vec::new(),
                         });
                     }
 
@@ -426,8 +1473,10 @@ fn parse_conversation(model: &String, lines: Vec<&str>) -> Conversation {
                         }
                     } else {
                         conversation.messages.push(Message {
-                            role: current_role.clone(),
+                            role: current_role,
                             content: String::from(line),
+                            tool_call_id: None,
+                            tool_calls: Vec::new(),
                         });
                     }
                 }
@@ -438,11 +1487,9 @@ fn parse_conversation(model: &String, lines: Vec<&str>) -> Conversation {
     conversation
 }
 
-fn update_conversation(conversation: &Conversation) -> Result<(), Box<dyn Error>> {
+fn update_conversation(session: &String, conversation: &Conversation) -> Result<(), Box<dyn Error>> {
     let conversation_path: PathBuf = match dirs::config_dir() {
-        Some(path) => path
-            .join("chatwith/")
-            .join(format!("{}{}", &conversation.model, ".conv")),
+        Some(path) => path.join("chatwith/").join(format!("{}{}", session, ".conv")),
         None => Err("No valid config path found in environment variables.")?,
     };
 